@@ -1,5 +1,6 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use near_sdk::{env, near_bindgen, Promise};
+use near_sdk::json_types::U128;
+use near_sdk::{env, ext_contract, near_bindgen, Gas, Promise, PromiseOrValue, PromiseResult};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -8,63 +9,262 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 const MIN_QUESTION_REWARD: u8 = 10;
 const ANSWER_PRICE: u8 = 1;
+const GAS_FOR_FT_TRANSFER: Gas = 10_000_000_000_000;
+const GAS_FOR_RESOLVE_TRANSFER: Gas = 5_000_000_000_000;
+/// Percentage of a question's reward that goes to the accepted answer; the
+/// rest is shared among the question's delegators. A question with no
+/// delegators pays its whole reward to the accepted answer.
+const ANSWERER_REWARD_PERCENT: u8 = 70;
 
-type Stakes = HashMap<String, u128>;
+/// Keyed by `(account_id, token_account_id)` so a single account's native
+/// NEAR stake and its stake in each NEP-141 token never commingle in one
+/// scalar; `token_account_id` is `None` for native NEAR.
+type Stakes = HashMap<(String, Option<String>), u128>;
+
+/// Minimal NEP-141 interface we need to pay rewards back out in the
+/// funding token.
+#[ext_contract(ext_fungible_token)]
+pub trait FungibleToken {
+    fn ft_transfer(&mut self, receiver_id: String, amount: U128, memo: Option<String>);
+}
+
+/// Carried in `ft_transfer_call`'s `msg` so `ft_on_transfer` knows what the
+/// deposited tokens are for.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "action")]
+enum FtMessage {
+    CreateQuestion {
+        content: String,
+        deadline: u64,
+        distribution_mode: DistributionMode,
+    },
+    UpvoteAnswer {
+        question_id: u32,
+        answer_id: u32,
+    },
+}
+
+/// Private callbacks that inspect the result of a reward transfer and roll
+/// back the state that was staged optimistically before the transfer.
+#[ext_contract(ext_self)]
+trait SelfResolve {
+    fn resolve_upvote(&mut self, question_id: u32, answer_id: u32, amount: u128);
+    fn resolve_award(
+        &mut self,
+        question_id: u32,
+        answer_id: u32,
+        stake_holder_id: String,
+        reward: u128,
+        token_account_id: Option<String>,
+        delegator_credits: Vec<DelegationCredit>,
+    );
+    fn resolve_reclaim(
+        &mut self,
+        question_id: u32,
+        question: Question,
+        stake_holder_id: String,
+        reward: u128,
+    );
+    fn resolve_distribute(
+        &mut self,
+        question_id: u32,
+        answer_id: u32,
+        stake_holder_id: String,
+        share: u128,
+        credits_paid: u128,
+        token_account_id: Option<String>,
+    );
+    fn resolve_undelegate(&mut self, question_id: u32, delegation: Delegation);
+}
+
+/// The two witnesses that can release a question's escrowed reward: the
+/// author picking a correct answer, or the deadline simply passing.
+enum Release {
+    OnCorrect,
+    AfterDeadline,
+}
+
+fn active_release(question: &Question) -> Release {
+    if env::block_timestamp() >= question.deadline {
+        Release::AfterDeadline
+    } else {
+        Release::OnCorrect
+    }
+}
 
 #[near_bindgen]
 #[derive(Default, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 pub struct Ledger {
     stakes: Stakes,
     questions: HashMap<u32, Question>,
+    /// Delegations curating a question's answers, keyed by question id.
+    delegations: HashMap<u32, Vec<Delegation>>,
+}
+
+/// A curator's stake on a question. Boosts the question's visibility and,
+/// once the author accepts an answer, earns a pro-rata share of the reward
+/// for delegators who staked before the correct answer was chosen.
+#[derive(Clone, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+pub struct Delegation {
+    delegator_account_id: String,
+    amount: u128,
+    delegated_at: u64,
+    earned_share: u128,
+    /// The question's funding token at the time of delegation, `None` for
+    /// native NEAR. `earned_share` is denominated in this token; `amount`
+    /// (the delegator's own stake) is always native NEAR, since `delegate`
+    /// takes it as an attached deposit regardless of the question's token.
+    token_account_id: Option<String>,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[derive(Clone, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 pub struct Question {
     content: String,
     reward: u128,
     author_account_id: String,
     answers: Vec<Answer>,
+    /// NEP-141 token the reward is denominated in, `None` for native NEAR.
+    token_account_id: Option<String>,
+    /// Block timestamp after which the reward is no longer held hostage to
+    /// an accepted answer and `reclaim_question` may refund it.
+    deadline: u64,
+    /// How `reward` is paid out: to the single answer the author marks
+    /// correct, or split across answers by upvote weight.
+    distribution_mode: DistributionMode,
+    /// Portion of `reward` already paid out by `distribute_reward`, so
+    /// `reclaim_question` only refunds the undistributed remainder.
+    distributed: u128,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+pub enum DistributionMode {
+    WinnerTakesAll,
+    ProportionalByUpvotes,
+}
+
+#[derive(Clone, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 pub struct Answer {
     id: u32,
     content: String,
     account_id: String,
     reward: u128,
     is_correct: bool,
+    /// Cumulative upvote weight this answer has earned, used to compute its
+    /// share under `DistributionMode::ProportionalByUpvotes`.
+    upvote_credits: u128,
+    /// Portion of `upvote_credits` already paid out by `distribute_reward`,
+    /// so repeated calls never reward the same upvote twice.
+    credits_observed: u128,
+}
+
+fn add_stake(stakes: &mut Stakes, account_id: String, token_account_id: Option<String>, amount: u128) {
+    *stakes.entry((account_id, token_account_id)).or_insert(0) += amount;
 }
 
-fn add_stake(stakes: &mut Stakes, account_id: String, amount: u128) {
-    *stakes.entry(account_id).or_insert(0) += amount;
+/// One delegator's cut of a payout, precise enough that `resolve_award` can
+/// undo exactly this credit if the answerer's transfer later fails.
+#[derive(Clone, Serialize, Deserialize)]
+struct DelegationCredit {
+    delegator_account_id: String,
+    amount: u128,
+}
+
+/// Credits each delegation's `earned_share` with its pro-rata cut of
+/// `total_share`, weighted by how much it staked and how long it had been
+/// locked in when the payout happened. Delegators weigh in only with the
+/// stake they locked in before this moment. Returns the total amount
+/// credited and the per-delegator breakdown, so the caller can both debit
+/// `stakes` by exactly that much (integer-division dust is left behind,
+/// same as `distribute_reward`) and undo the credits individually if the
+/// payout this was staged for later fails.
+fn distribute_delegator_shares(
+    delegations: &mut [Delegation],
+    total_share: u128,
+    now: u64,
+) -> (u128, Vec<DelegationCredit>) {
+    let total_weight: u128 = delegations
+        .iter()
+        .map(|delegation| {
+            delegation.amount * (now.saturating_sub(delegation.delegated_at) as u128 + 1)
+        })
+        .sum();
+    if total_weight == 0 {
+        return (0, vec![]);
+    }
+
+    let mut distributed = 0u128;
+    let mut credits = Vec::with_capacity(delegations.len());
+    for delegation in delegations.iter_mut() {
+        let weight = delegation.amount * (now.saturating_sub(delegation.delegated_at) as u128 + 1);
+        let share = total_share * weight / total_weight;
+        if share == 0 {
+            continue;
+        }
+        delegation.earned_share += share;
+        distributed += share;
+        credits.push(DelegationCredit {
+            delegator_account_id: delegation.delegator_account_id.clone(),
+            amount: share,
+        });
+    }
+    (distributed, credits)
 }
 
 fn award_answer_author(
     stakes: &mut Stakes,
+    question_id: u32,
     stake_holder_id: String,
     answer: &Answer,
     reward: u128,
+    token_account_id: &Option<String>,
+    delegator_credits: Vec<DelegationCredit>,
 ) -> bool {
+    let stake_key = (stake_holder_id.clone(), token_account_id.clone());
+    assert!(stakes.contains_key(&stake_key), "Stake holder has no deposit");
     assert!(
-        stakes.contains_key(&stake_holder_id),
-        "Stake holder has no deposit"
-    );
-    assert!(
-        *stakes.get(&stake_holder_id).unwrap_or(&0u128) >= reward,
+        *stakes.get(&stake_key).unwrap_or(&0u128) >= reward,
         "Stake holder has not enough deposit"
     );
 
-    // Transfer reward
-    Promise::new(answer.account_id.clone()).transfer(reward);
+    // Transfer reward, in the question's funding token if it has one, and
+    // roll back the staged stake/reward mutation below if it fails.
+    let transfer = match token_account_id {
+        Some(token_account_id) => ext_fungible_token::ft_transfer(
+            answer.account_id.clone(),
+            reward.into(),
+            None,
+            token_account_id,
+            1,
+            GAS_FOR_FT_TRANSFER,
+        ),
+        None => Promise::new(answer.account_id.clone()).transfer(reward),
+    };
+    transfer.then(ext_self::resolve_award(
+        question_id,
+        answer.id,
+        stake_holder_id.clone(),
+        reward,
+        token_account_id.clone(),
+        delegator_credits,
+        &env::current_account_id(),
+        0,
+        GAS_FOR_RESOLVE_TRANSFER,
+    ));
+
     // Decrease stake holder deposit
-    *stakes.entry(stake_holder_id).or_insert(0) -= reward;
+    *stakes.entry(stake_key).or_insert(0) -= reward;
     true
 }
 
 #[near_bindgen]
 impl Ledger {
     #[payable]
-    pub fn create_question(&mut self, content: String) {
+    pub fn create_question(
+        &mut self,
+        content: String,
+        deadline: u64,
+        distribution_mode: DistributionMode,
+    ) {
         // We want to ensure account attached enough deposit for the reward
         let attached_deposit = env::attached_deposit();
         assert!(
@@ -72,6 +272,10 @@ impl Ledger {
             "Min question reward is {}",
             MIN_QUESTION_REWARD
         );
+        assert!(
+            deadline > env::block_timestamp(),
+            "Deadline must be in the future"
+        );
 
         let sender_id = env::signer_account_id();
         let last_question_id = *self.questions.keys().max().unwrap_or(&0u32);
@@ -82,9 +286,106 @@ impl Ledger {
                 reward: attached_deposit,
                 author_account_id: sender_id.clone(),
                 answers: vec![],
+                token_account_id: None,
+                deadline,
+                distribution_mode,
+                distributed: 0,
             },
         );
-        add_stake(&mut self.stakes, sender_id.clone(), attached_deposit);
+        add_stake(&mut self.stakes, sender_id.clone(), None, attached_deposit);
+    }
+
+    /// NEP-141 entry point: a token contract calls this via `ft_transfer_call`
+    /// to fund a question (or upvote an answer) with fungible tokens instead
+    /// of native NEAR. Returns the portion of `amount` that should be
+    /// refunded to `sender_id`.
+    pub fn ft_on_transfer(
+        &mut self,
+        sender_id: String,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let token_account_id = env::predecessor_account_id();
+        let amount: u128 = amount.into();
+        let message: FtMessage =
+            serde_json::from_str(&msg).unwrap_or_else(|_| panic!("Invalid ft_on_transfer msg"));
+
+        match message {
+            FtMessage::CreateQuestion {
+                content,
+                deadline,
+                distribution_mode,
+            } => {
+                if amount < MIN_QUESTION_REWARD.into() {
+                    // Deposit too small to fund a question, refund it all
+                    return PromiseOrValue::Value(amount.into());
+                }
+                assert!(
+                    deadline > env::block_timestamp(),
+                    "Deadline must be in the future"
+                );
+                let last_question_id = *self.questions.keys().max().unwrap_or(&0u32);
+                self.questions.insert(
+                    last_question_id + 1,
+                    Question {
+                        content,
+                        reward: amount,
+                        author_account_id: sender_id.clone(),
+                        answers: vec![],
+                        token_account_id: Some(token_account_id.clone()),
+                        deadline,
+                        distribution_mode,
+                        distributed: 0,
+                    },
+                );
+                add_stake(&mut self.stakes, sender_id, Some(token_account_id), amount);
+                PromiseOrValue::Value(0.into())
+            }
+            FtMessage::UpvoteAnswer {
+                question_id,
+                answer_id,
+            } => {
+                let question = self
+                    .questions
+                    .get_mut(&question_id)
+                    .unwrap_or_else(|| panic!("Question with id {} not found", question_id));
+                assert_eq!(
+                    question.token_account_id,
+                    Some(token_account_id.clone()),
+                    "Question is not funded in this token"
+                );
+                let answer = question
+                    .answers
+                    .iter_mut()
+                    .find(|answer| answer.id == answer_id);
+                let answer = match answer {
+                    Some(v) => v,
+                    None => panic!("Answer with id {} not found", answer_id),
+                };
+
+                // Forward the upvote deposit, staging the reward bump now and
+                // rolling it back in resolve_upvote if the transfer fails.
+                ext_fungible_token::ft_transfer(
+                    answer.account_id.clone(),
+                    amount.into(),
+                    None,
+                    &token_account_id,
+                    1,
+                    GAS_FOR_FT_TRANSFER,
+                )
+                .then(ext_self::resolve_upvote(
+                    question_id,
+                    answer_id,
+                    amount,
+                    &env::current_account_id(),
+                    0,
+                    GAS_FOR_RESOLVE_TRANSFER,
+                ));
+                answer.reward += amount;
+                answer.upvote_credits += amount;
+                PromiseOrValue::Value(0.into())
+            }
+        }
     }
 
     #[payable]
@@ -117,6 +418,8 @@ impl Ledger {
             account_id: sender_id,
             reward: 0,
             is_correct: false,
+            upvote_credits: 0,
+            credits_observed: 0,
         };
         self.questions
             .get_mut(&question_id)
@@ -133,16 +436,16 @@ impl Ledger {
             "To upvote the answer your deposit have to be greater than 0"
         );
 
-        assert!(
-            self.questions.contains_key(&question_id),
-            "Question with id {} not found",
-            &question_id
-        );
-
-        let answer = self
+        let question = self
             .questions
             .get_mut(&question_id)
-            .unwrap()
+            .unwrap_or_else(|| panic!("Question with id {} not found", &question_id));
+        assert_eq!(
+            question.token_account_id, None,
+            "Question is funded in a NEP-141 token, upvote via ft_transfer_call instead"
+        );
+
+        let answer = question
             .answers
             .iter_mut()
             .find(|answer| answer.id == answer_id);
@@ -152,13 +455,96 @@ impl Ledger {
             None => panic!("Answer with id {} not found", &answer_id),
         };
 
-        // transfer deposit to answer author
-        Promise::new(answer.account_id.clone()).transfer(attached_deposit);
+        // transfer deposit to answer author, staging the reward bump now and
+        // rolling it back in resolve_upvote if the transfer fails
+        Promise::new(answer.account_id.clone())
+            .transfer(attached_deposit)
+            .then(ext_self::resolve_upvote(
+                question_id,
+                answer_id,
+                attached_deposit,
+                &env::current_account_id(),
+                0,
+                GAS_FOR_RESOLVE_TRANSFER,
+            ));
         answer.reward += attached_deposit;
+        answer.upvote_credits += attached_deposit;
 
         answer
     }
 
+    #[private]
+    pub fn resolve_upvote(&mut self, question_id: u32, answer_id: u32, amount: u128) {
+        if env::predecessor_account_id() != env::current_account_id() {
+            panic!("resolve_upvote can only be called as a transfer callback");
+        }
+        if let PromiseResult::Failed = env::promise_result(0) {
+            if let Some(answer) = self
+                .questions
+                .get_mut(&question_id)
+                .and_then(|question| question.answers.iter_mut().find(|a| a.id == answer_id))
+            {
+                answer.reward -= amount;
+                answer.upvote_credits -= amount;
+            }
+        }
+    }
+
+    #[private]
+    pub fn resolve_award(
+        &mut self,
+        question_id: u32,
+        answer_id: u32,
+        stake_holder_id: String,
+        reward: u128,
+        token_account_id: Option<String>,
+        delegator_credits: Vec<DelegationCredit>,
+    ) {
+        if env::predecessor_account_id() != env::current_account_id() {
+            panic!("resolve_award can only be called as a transfer callback");
+        }
+        if let PromiseResult::Failed = env::promise_result(0) {
+            add_stake(
+                &mut self.stakes,
+                stake_holder_id.clone(),
+                token_account_id.clone(),
+                reward,
+            );
+            if let Some(answer) = self
+                .questions
+                .get_mut(&question_id)
+                .and_then(|question| question.answers.iter_mut().find(|a| a.id == answer_id))
+            {
+                answer.is_correct = false;
+                answer.reward -= reward;
+            }
+
+            // Reverse the delegator shares staged alongside the answerer's
+            // reward, so a retried set_correct_answer can't double-credit
+            // them once is_correct is false again.
+            if !delegator_credits.is_empty() {
+                let distributed: u128 = delegator_credits.iter().map(|c| c.amount).sum();
+                add_stake(&mut self.stakes, stake_holder_id, token_account_id, distributed);
+                if let Some(delegations) = self.delegations.get_mut(&question_id) {
+                    // Credits were staged in delegation order, so walk both in
+                    // step to undo each one on the delegation it was paid to,
+                    // even when one account holds several delegations here.
+                    let mut credits = delegator_credits.iter().peekable();
+                    for delegation in delegations.iter_mut() {
+                        if let Some(credit) = credits.peek() {
+                            if delegation.delegator_account_id == credit.delegator_account_id
+                                && delegation.earned_share >= credit.amount
+                            {
+                                delegation.earned_share -= credit.amount;
+                                credits.next();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     pub fn set_correct_answer<'a>(&'a mut self, question_id: u32, answer_id: u32) -> &'a Answer {
         let signer_id = env::signer_account_id();
         assert!(
@@ -171,6 +557,18 @@ impl Ledger {
             &signer_id,
             "Signer is not an author of the question and must not select what answer is correct"
         );
+        {
+            let question = self.questions.get(&question_id).unwrap();
+            let total_upvote_credits: u128 =
+                question.answers.iter().map(|a| a.upvote_credits).sum();
+            // A question split by upvotes still falls back to winner-take-all
+            // here if it never received any upvotes to weigh the split by.
+            assert!(
+                question.distribution_mode == DistributionMode::WinnerTakesAll
+                    || total_upvote_credits == 0,
+                "Question reward is split by upvotes, call distribute_reward instead"
+            );
+        }
         assert!(
             self.questions
                 .get(&question_id)
@@ -182,8 +580,58 @@ impl Ledger {
                 == 0,
             "Correct answer for this question have been selected already"
         );
+        assert!(
+            matches!(
+                active_release(self.questions.get(&question_id).unwrap()),
+                Release::OnCorrect
+            ),
+            "Question deadline has passed, call reclaim_question instead"
+        );
 
         let question_reward = self.questions.get(&question_id).unwrap().reward;
+        let token_account_id = self
+            .questions
+            .get(&question_id)
+            .unwrap()
+            .token_account_id
+            .clone();
+        let base_answerer_share = question_reward * ANSWERER_REWARD_PERCENT as u128 / 100;
+        let delegator_share = question_reward - base_answerer_share;
+
+        // Stage the delegator split before paying the answerer, so a failed
+        // transfer can roll back both pieces together via resolve_award.
+        let (distributed, delegator_credits) = if delegator_share > 0 {
+            match self.delegations.get_mut(&question_id) {
+                Some(delegations) if !delegations.is_empty() => {
+                    distribute_delegator_shares(delegations, delegator_share, env::block_timestamp())
+                }
+                _ => (0, vec![]),
+            }
+        } else {
+            (0, vec![])
+        };
+
+        // No delegators staked on this question at all: route the whole
+        // bounty to the answerer instead of stranding the delegator share in
+        // the author's stake forever. If delegators exist but their shares
+        // all rounded down to 0 (dust, not absence), leave the fallback
+        // alone and just eat the dust the same way distribute_reward does.
+        let has_delegators = self
+            .delegations
+            .get(&question_id)
+            .map_or(false, |delegations| !delegations.is_empty());
+        let answerer_share = if has_delegators {
+            if distributed > 0 {
+                *self
+                    .stakes
+                    .entry((signer_id.clone(), token_account_id.clone()))
+                    .or_insert(0) -= distributed;
+            }
+            base_answerer_share
+        } else {
+            question_reward
+        };
+
         let answer_to_be_correct = self
             .questions
             .get_mut(&question_id)
@@ -202,18 +650,317 @@ impl Ledger {
         );
         if award_answer_author(
             &mut self.stakes,
-            signer_id,
+            question_id,
+            signer_id.clone(),
             &answer_to_be_correct,
-            question_reward,
+            answerer_share,
+            &token_account_id,
+            delegator_credits,
         ) {
             answer_to_be_correct.is_correct = true;
-            answer_to_be_correct.reward += question_reward;
+            answer_to_be_correct.reward += answerer_share;
         } else {
             panic!("Unable to reward the correct answer author");
         }
         answer_to_be_correct
     }
 
+    /// Witnessed by the `AfterDeadline` release: once a question's deadline
+    /// has passed without a correct answer, anyone may trigger a refund of
+    /// the escrowed reward back to the author.
+    pub fn reclaim_question(&mut self, question_id: u32) -> bool {
+        let question = self
+            .questions
+            .get(&question_id)
+            .unwrap_or_else(|| panic!("Question with id {} not found", question_id));
+        assert!(
+            matches!(active_release(question), Release::AfterDeadline),
+            "Question deadline has not passed yet"
+        );
+        assert!(
+            !question.answers.iter().any(|answer| answer.is_correct),
+            "Question already has a correct answer and cannot be reclaimed"
+        );
+
+        // distribute_reward may already have paid out part of the bounty;
+        // only the remainder is still owed to the author.
+        let undistributed = question.reward - question.distributed;
+        let author_account_id = question.author_account_id.clone();
+        let token_account_id = question.token_account_id.clone();
+        let stake_key = (author_account_id.clone(), token_account_id.clone());
+        assert!(
+            *self.stakes.get(&stake_key).unwrap_or(&0u128) >= undistributed,
+            "Author stake is lower than the question reward"
+        );
+
+        // Staging the removal now and rolling it back in resolve_reclaim if
+        // the refund transfer fails
+        let question_snapshot = self.questions.remove(&question_id).unwrap();
+        let transfer = match &token_account_id {
+            Some(token_account_id) => ext_fungible_token::ft_transfer(
+                author_account_id.clone(),
+                undistributed.into(),
+                None,
+                token_account_id,
+                1,
+                GAS_FOR_FT_TRANSFER,
+            ),
+            None => Promise::new(author_account_id.clone()).transfer(undistributed),
+        };
+        transfer.then(ext_self::resolve_reclaim(
+            question_id,
+            question_snapshot,
+            author_account_id.clone(),
+            undistributed,
+            &env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_TRANSFER,
+        ));
+
+        *self.stakes.entry(stake_key).or_insert(0) -= undistributed;
+        true
+    }
+
+    #[private]
+    pub fn resolve_reclaim(
+        &mut self,
+        question_id: u32,
+        question: Question,
+        stake_holder_id: String,
+        reward: u128,
+    ) {
+        if env::predecessor_account_id() != env::current_account_id() {
+            panic!("resolve_reclaim can only be called as a transfer callback");
+        }
+        if let PromiseResult::Failed = env::promise_result(0) {
+            add_stake(
+                &mut self.stakes,
+                stake_holder_id,
+                question.token_account_id.clone(),
+                reward,
+            );
+            self.questions.insert(question_id, question);
+        }
+    }
+
+    /// Pays out `question.reward` split across answers in proportion to
+    /// their upvotes, for questions created with
+    /// `DistributionMode::ProportionalByUpvotes`. Safe to call repeatedly:
+    /// only credits accrued since the last call are rewarded.
+    pub fn distribute_reward(&mut self, question_id: u32) {
+        let question = self
+            .questions
+            .get(&question_id)
+            .unwrap_or_else(|| panic!("Question with id {} not found", question_id));
+        assert_eq!(
+            question.distribution_mode,
+            DistributionMode::ProportionalByUpvotes,
+            "Question reward goes to a single correct answer, call set_correct_answer instead"
+        );
+        assert!(
+            matches!(active_release(question), Release::OnCorrect),
+            "Question deadline has passed, call reclaim_question instead"
+        );
+
+        let total_upvote_credits: u128 = question.answers.iter().map(|a| a.upvote_credits).sum();
+        // No upvotes yet to weigh the split by; set_correct_answer falls back
+        // to winner-take-all for this question in that case instead.
+        assert!(
+            total_upvote_credits > 0,
+            "No upvotes recorded yet; call set_correct_answer for winner-take-all instead"
+        );
+        // Priced off what's still owed and still un-rewarded, not the
+        // lifetime totals, so repeated calls can never pay out more than
+        // `question.reward` as credits keep accruing across calls.
+        let total_credits_observed: u128 =
+            question.answers.iter().map(|a| a.credits_observed).sum();
+        let remaining_credits = total_upvote_credits - total_credits_observed;
+        let remaining_reward = question.reward - question.distributed;
+        let point_value = if remaining_credits > 0 {
+            remaining_reward / remaining_credits
+        } else {
+            0
+        };
+        let author_account_id = question.author_account_id.clone();
+        let token_account_id = question.token_account_id.clone();
+
+        let stake_key = (author_account_id.clone(), token_account_id.clone());
+        let question = self.questions.get_mut(&question_id).unwrap();
+        for answer in question.answers.iter_mut() {
+            let newly_accrued = answer.upvote_credits - answer.credits_observed;
+            let share = newly_accrued * point_value;
+            if share == 0 {
+                continue;
+            }
+
+            assert!(
+                *self.stakes.get(&stake_key).unwrap_or(&0u128) >= share,
+                "Author stake is lower than this answer's share"
+            );
+
+            let transfer = match &token_account_id {
+                Some(token_account_id) => ext_fungible_token::ft_transfer(
+                    answer.account_id.clone(),
+                    share.into(),
+                    None,
+                    token_account_id,
+                    1,
+                    GAS_FOR_FT_TRANSFER,
+                ),
+                None => Promise::new(answer.account_id.clone()).transfer(share),
+            };
+            transfer.then(ext_self::resolve_distribute(
+                question_id,
+                answer.id,
+                author_account_id.clone(),
+                share,
+                newly_accrued,
+                token_account_id.clone(),
+                &env::current_account_id(),
+                0,
+                GAS_FOR_RESOLVE_TRANSFER,
+            ));
+
+            answer.credits_observed = answer.upvote_credits;
+            question.distributed += share;
+            *self.stakes.entry(stake_key.clone()).or_insert(0) -= share;
+        }
+    }
+
+    #[private]
+    pub fn resolve_distribute(
+        &mut self,
+        question_id: u32,
+        answer_id: u32,
+        stake_holder_id: String,
+        share: u128,
+        credits_paid: u128,
+        token_account_id: Option<String>,
+    ) {
+        if env::predecessor_account_id() != env::current_account_id() {
+            panic!("resolve_distribute can only be called as a transfer callback");
+        }
+        if let PromiseResult::Failed = env::promise_result(0) {
+            add_stake(&mut self.stakes, stake_holder_id, token_account_id, share);
+            if let Some(question) = self.questions.get_mut(&question_id) {
+                question.distributed -= share;
+                if let Some(answer) = question.answers.iter_mut().find(|a| a.id == answer_id) {
+                    answer.credits_observed -= credits_paid;
+                }
+            }
+        }
+    }
+
+    /// Stake behind a question to signal its quality and earn a cut of its
+    /// reward if it resolves with a correct answer while the stake is in.
+    #[payable]
+    pub fn delegate(&mut self, question_id: u32) {
+        let amount = env::attached_deposit();
+        assert!(amount > 0, "Delegation deposit must be greater than 0");
+        let question = self
+            .questions
+            .get(&question_id)
+            .unwrap_or_else(|| panic!("Question with id {} not found", question_id));
+        let token_account_id = question.token_account_id.clone();
+
+        let delegator_account_id = env::signer_account_id();
+        self.delegations
+            .entry(question_id)
+            .or_insert_with(Vec::new)
+            .push(Delegation {
+                delegator_account_id,
+                amount,
+                delegated_at: env::block_timestamp(),
+                earned_share: 0,
+                token_account_id,
+            });
+    }
+
+    /// Withdraws a delegation's stake plus any reward share it has earned,
+    /// rolling the withdrawal back in `resolve_undelegate` if the transfer
+    /// fails.
+    pub fn undelegate(&mut self, question_id: u32) -> Promise {
+        let delegator_account_id = env::signer_account_id();
+        let delegations = self
+            .delegations
+            .get_mut(&question_id)
+            .unwrap_or_else(|| panic!("No delegations for question {}", question_id));
+        let position = delegations
+            .iter()
+            .position(|delegation| delegation.delegator_account_id == delegator_account_id)
+            .unwrap_or_else(|| {
+                panic!(
+                    "{} has no delegation on question {}",
+                    &delegator_account_id, question_id
+                )
+            });
+        let delegation = delegations.remove(position);
+
+        // `amount` (the delegator's own stake) is always native NEAR;
+        // `earned_share` is denominated in the question's funding token, so
+        // pay it back in kind via ft_transfer instead of as native NEAR.
+        let transfer = match &delegation.token_account_id {
+            Some(token_account_id) if delegation.earned_share > 0 => {
+                Promise::new(delegator_account_id.clone())
+                    .transfer(delegation.amount)
+                    .and(ext_fungible_token::ft_transfer(
+                        delegator_account_id,
+                        delegation.earned_share.into(),
+                        None,
+                        token_account_id,
+                        1,
+                        GAS_FOR_FT_TRANSFER,
+                    ))
+            }
+            _ => Promise::new(delegator_account_id).transfer(delegation.amount + delegation.earned_share),
+        };
+
+        transfer.then(ext_self::resolve_undelegate(
+            question_id,
+            delegation,
+            &env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_TRANSFER,
+        ))
+    }
+
+    #[private]
+    pub fn resolve_undelegate(&mut self, question_id: u32, delegation: Delegation) {
+        if env::predecessor_account_id() != env::current_account_id() {
+            panic!("resolve_undelegate can only be called as a transfer callback");
+        }
+        // A token-funded withdrawal sends the stake and the earned share as
+        // two joined transfers; put back only the part whose transfer failed.
+        let mut delegation = delegation;
+        let stake_failed = matches!(env::promise_result(0), PromiseResult::Failed);
+        let share_failed = if env::promise_results_count() > 1 {
+            matches!(env::promise_result(1), PromiseResult::Failed)
+        } else {
+            stake_failed
+        };
+        if !stake_failed {
+            delegation.amount = 0;
+        }
+        if !share_failed {
+            delegation.earned_share = 0;
+        }
+        if stake_failed || share_failed {
+            self.delegations
+                .entry(question_id)
+                .or_insert_with(Vec::new)
+                .push(delegation);
+        }
+    }
+
+    pub fn list_delegations(&self, account_id: String) -> Vec<Delegation> {
+        self.delegations
+            .values()
+            .flatten()
+            .filter(|delegation| delegation.delegator_account_id == account_id)
+            .cloned()
+            .collect()
+    }
+
     pub fn list_questions(&self) -> &HashMap<u32, Question> {
         &self.questions
     }
@@ -226,7 +973,7 @@ impl Ledger {
 mod tests {
     use super::*;
     use near_sdk::MockedBlockchain;
-    use near_sdk::{testing_env, AccountId, VMContext};
+    use near_sdk::{testing_env, AccountId, RuntimeFeesConfig, VMConfig, VMContext};
 
     fn alice() -> AccountId {
         "alice".to_string()
@@ -268,18 +1015,26 @@ mod tests {
         let mut contract = Ledger {
             stakes: HashMap::new(),
             questions: HashMap::new(),
+            delegations: HashMap::new(),
         };
-        contract.create_question("How to I look?".to_string());
+        contract.create_question(
+            "How to I look?".to_string(),
+            100,
+            DistributionMode::WinnerTakesAll,
+        );
         assert_eq!(contract.questions.len(), 1);
-        assert_eq!(*contract.stakes.get(&alice()).unwrap_or(&0u128), 10u128);
+        assert_eq!(
+            *contract.stakes.get(&(alice(), None)).unwrap_or(&0u128),
+            10u128
+        );
     }
 
     #[test]
     fn bob_can_answer_alice_question() {
         let context = get_context(bob(), 1);
         testing_env!(context);
-        let mut stakes: HashMap<String, u128> = HashMap::new();
-        stakes.insert(alice(), 10);
+        let mut stakes: Stakes = HashMap::new();
+        stakes.insert((alice(), None), 10);
 
         let mut questions: HashMap<u32, Question> = HashMap::new();
         questions.insert(
@@ -289,11 +1044,19 @@ mod tests {
                 reward: 10,
                 author_account_id: alice(),
                 answers: vec![],
+                token_account_id: None,
+                deadline: 100,
+                distribution_mode: DistributionMode::WinnerTakesAll,
+                distributed: 0,
             },
         );
-        let mut contract = Ledger { stakes, questions };
+        let mut contract = Ledger {
+            stakes,
+            questions,
+            delegations: HashMap::new(),
+        };
         assert_eq!(contract.questions.len(), 1);
-        assert_eq!(contract.stakes.get(&"alice".to_string()).unwrap(), &10u128);
+        assert_eq!(contract.stakes.get(&(alice(), None)).unwrap(), &10u128);
 
         contract.create_answer(1, "You look great!".to_string());
 
@@ -318,13 +1081,20 @@ mod tests {
                     account_id: bob(),
                     reward: 0,
                     is_correct: false,
+                    upvote_credits: 0,
+                    credits_observed: 0,
                 }],
+                token_account_id: None,
+                deadline: 100,
+                distribution_mode: DistributionMode::WinnerTakesAll,
+                distributed: 0,
             },
         );
 
         let mut contract = Ledger {
             stakes: HashMap::new(),
             questions,
+            delegations: HashMap::new(),
         };
 
         let answer = contract.upvote_answer(1, 1);
@@ -349,13 +1119,23 @@ mod tests {
                     account_id: bob(),
                     reward: 0,
                     is_correct: false,
+                    upvote_credits: 0,
+                    credits_observed: 0,
                 }],
+                token_account_id: None,
+                deadline: 100,
+                distribution_mode: DistributionMode::WinnerTakesAll,
+                distributed: 0,
             },
         );
         let mut stakes: Stakes = HashMap::new();
-        stakes.insert(alice().clone(), 10);
+        stakes.insert((alice(), None), 10);
 
-        let mut contract = Ledger { stakes, questions };
+        let mut contract = Ledger {
+            stakes,
+            questions,
+            delegations: HashMap::new(),
+        };
 
         contract.set_correct_answer(1, 1);
         assert_eq!(
@@ -370,4 +1150,696 @@ mod tests {
             true
         );
     }
+
+    #[test]
+    fn author_can_reclaim_question_after_deadline_passes() {
+        let mut context = get_context(alice(), 0);
+        context.block_timestamp = 200;
+        testing_env!(context);
+
+        let mut questions: HashMap<u32, Question> = HashMap::new();
+        questions.insert(
+            1,
+            Question {
+                content: "How do I look?".to_string(),
+                reward: 10,
+                author_account_id: alice(),
+                answers: vec![],
+                token_account_id: None,
+                deadline: 100,
+                distribution_mode: DistributionMode::WinnerTakesAll,
+                distributed: 0,
+            },
+        );
+        let mut stakes: Stakes = HashMap::new();
+        stakes.insert((alice(), None), 10);
+
+        let mut contract = Ledger {
+            stakes,
+            questions,
+            delegations: HashMap::new(),
+        };
+
+        assert!(contract.reclaim_question(1));
+        assert!(!contract.questions.contains_key(&1u32));
+    }
+
+    #[test]
+    fn reward_is_split_across_answers_by_upvotes() {
+        let context = get_context(alice(), 0);
+        testing_env!(context);
+
+        let mut questions: HashMap<u32, Question> = HashMap::new();
+        questions.insert(
+            1,
+            Question {
+                content: "How do I look?".to_string(),
+                reward: 12,
+                author_account_id: alice(),
+                answers: vec![
+                    Answer {
+                        id: 1,
+                        content: "Perfect".to_string(),
+                        account_id: bob(),
+                        reward: 0,
+                        is_correct: false,
+                        upvote_credits: 2,
+                        credits_observed: 0,
+                    },
+                    Answer {
+                        id: 2,
+                        content: "Great".to_string(),
+                        account_id: robin(),
+                        reward: 0,
+                        is_correct: false,
+                        upvote_credits: 1,
+                        credits_observed: 0,
+                    },
+                ],
+                token_account_id: None,
+                deadline: 100,
+                distribution_mode: DistributionMode::ProportionalByUpvotes,
+                distributed: 0,
+            },
+        );
+        let mut stakes: Stakes = HashMap::new();
+        stakes.insert((alice(), None), 12);
+
+        let mut contract = Ledger {
+            stakes,
+            questions,
+            delegations: HashMap::new(),
+        };
+
+        contract.distribute_reward(1);
+
+        let question = contract.questions.get(&1u32).unwrap();
+        assert_eq!(question.answers[0].credits_observed, 2);
+        assert_eq!(question.answers[1].credits_observed, 1);
+        assert_eq!(*contract.stakes.get(&(alice(), None)).unwrap(), 0u128);
+    }
+
+    #[test]
+    fn distribute_reward_never_pays_out_more_than_the_reward_across_calls() {
+        let context = get_context(alice(), 0);
+        testing_env!(context);
+
+        let mut questions: HashMap<u32, Question> = HashMap::new();
+        questions.insert(
+            1,
+            Question {
+                content: "How do I look?".to_string(),
+                reward: 12,
+                author_account_id: alice(),
+                answers: vec![Answer {
+                    id: 1,
+                    content: "Perfect".to_string(),
+                    account_id: bob(),
+                    reward: 0,
+                    is_correct: false,
+                    upvote_credits: 2,
+                    credits_observed: 0,
+                }],
+                token_account_id: None,
+                deadline: 100,
+                distribution_mode: DistributionMode::ProportionalByUpvotes,
+                distributed: 0,
+            },
+        );
+        let mut stakes: Stakes = HashMap::new();
+        stakes.insert((alice(), None), 12);
+
+        let mut contract = Ledger {
+            stakes,
+            questions,
+            delegations: HashMap::new(),
+        };
+
+        // First call pays out the whole reward against the 2 credits seen so
+        // far; a later upvote bumping the lifetime credit total must not
+        // re-price the already-paid portion and overpay on the next call.
+        contract.distribute_reward(1);
+        assert_eq!(*contract.stakes.get(&(alice(), None)).unwrap(), 0u128);
+
+        contract
+            .questions
+            .get_mut(&1u32)
+            .unwrap()
+            .answers[0]
+            .upvote_credits += 1;
+
+        contract.distribute_reward(1);
+
+        let question = contract.questions.get(&1u32).unwrap();
+        assert_eq!(question.distributed, 12);
+        assert_eq!(*contract.stakes.get(&(alice(), None)).unwrap(), 0u128);
+    }
+
+    #[test]
+    fn reclaim_refunds_only_the_undistributed_remainder() {
+        let mut context = get_context(alice(), 0);
+        context.block_timestamp = 200;
+        testing_env!(context);
+
+        let mut questions: HashMap<u32, Question> = HashMap::new();
+        questions.insert(
+            1,
+            Question {
+                content: "How do I look?".to_string(),
+                reward: 10,
+                author_account_id: alice(),
+                answers: vec![],
+                token_account_id: None,
+                deadline: 100,
+                distribution_mode: DistributionMode::ProportionalByUpvotes,
+                // distribute_reward already paid out 4 of the 10 before the
+                // deadline passed; only the remaining 6 should be reclaimable.
+                distributed: 4,
+            },
+        );
+        let mut stakes: Stakes = HashMap::new();
+        stakes.insert((alice(), None), 6);
+
+        let mut contract = Ledger {
+            stakes,
+            questions,
+            delegations: HashMap::new(),
+        };
+
+        assert!(contract.reclaim_question(1));
+        assert_eq!(*contract.stakes.get(&(alice(), None)).unwrap(), 0u128);
+    }
+
+    #[test]
+    fn set_correct_answer_falls_back_to_winner_takes_all_without_upvotes() {
+        let context = get_context(alice(), 0);
+        testing_env!(context);
+
+        let mut questions: HashMap<u32, Question> = HashMap::new();
+        questions.insert(
+            1,
+            Question {
+                content: "How do I look?".to_string(),
+                reward: 10,
+                author_account_id: alice(),
+                answers: vec![Answer {
+                    id: 1,
+                    content: "Perfect".to_string(),
+                    account_id: bob(),
+                    reward: 0,
+                    is_correct: false,
+                    upvote_credits: 0,
+                    credits_observed: 0,
+                }],
+                token_account_id: None,
+                deadline: 100,
+                distribution_mode: DistributionMode::ProportionalByUpvotes,
+                distributed: 0,
+            },
+        );
+        let mut stakes: Stakes = HashMap::new();
+        stakes.insert((alice(), None), 10);
+
+        let mut contract = Ledger {
+            stakes,
+            questions,
+            delegations: HashMap::new(),
+        };
+
+        contract.set_correct_answer(1, 1);
+        assert!(contract.questions.get(&1u32).unwrap().answers[0].is_correct);
+    }
+
+    #[test]
+    fn delegator_earns_share_of_correct_answer_reward() {
+        let context = get_context(alice(), 0);
+        testing_env!(context);
+
+        let mut questions: HashMap<u32, Question> = HashMap::new();
+        questions.insert(
+            1,
+            Question {
+                content: "How do I look?".to_string(),
+                reward: 10,
+                author_account_id: alice(),
+                answers: vec![Answer {
+                    id: 1,
+                    content: "Perfect".to_string(),
+                    account_id: bob(),
+                    reward: 0,
+                    is_correct: false,
+                    upvote_credits: 0,
+                    credits_observed: 0,
+                }],
+                token_account_id: None,
+                deadline: 100,
+                distribution_mode: DistributionMode::WinnerTakesAll,
+                distributed: 0,
+            },
+        );
+        let mut stakes: Stakes = HashMap::new();
+        stakes.insert((alice(), None), 10);
+        let mut delegations: HashMap<u32, Vec<Delegation>> = HashMap::new();
+        delegations.insert(
+            1,
+            vec![Delegation {
+                delegator_account_id: robin(),
+                amount: 10,
+                delegated_at: 0,
+                earned_share: 0,
+                token_account_id: None,
+            }],
+        );
+
+        let mut contract = Ledger {
+            stakes,
+            questions,
+            delegations,
+        };
+
+        contract.set_correct_answer(1, 1);
+
+        assert_eq!(contract.delegations.get(&1u32).unwrap()[0].earned_share, 3);
+        assert_eq!(*contract.stakes.get(&(alice(), None)).unwrap(), 0u128);
+    }
+
+    #[test]
+    fn set_correct_answer_keeps_answerer_share_when_delegator_shares_round_to_zero() {
+        let context = get_context(alice(), 0);
+        testing_env!(context);
+
+        let mut questions: HashMap<u32, Question> = HashMap::new();
+        questions.insert(
+            1,
+            Question {
+                content: "How do I look?".to_string(),
+                reward: 10,
+                author_account_id: alice(),
+                answers: vec![Answer {
+                    id: 1,
+                    content: "Perfect".to_string(),
+                    account_id: bob(),
+                    reward: 0,
+                    is_correct: false,
+                    upvote_credits: 0,
+                    credits_observed: 0,
+                }],
+                token_account_id: None,
+                deadline: 100,
+                distribution_mode: DistributionMode::WinnerTakesAll,
+                distributed: 0,
+            },
+        );
+        let mut stakes: Stakes = HashMap::new();
+        stakes.insert((alice(), None), 10);
+        // A 30% delegator_share of 3 split four equal ways truncates to 0 per
+        // delegator (3 * 1 / 4 == 0): real delegations exist, but their cut
+        // is just dust, not an empty delegations list. The answerer must
+        // still only get the 70% answerer share, not the whole reward.
+        let mut delegations: HashMap<u32, Vec<Delegation>> = HashMap::new();
+        delegations.insert(
+            1,
+            vec![
+                Delegation {
+                    delegator_account_id: robin(),
+                    amount: 1,
+                    delegated_at: 0,
+                    earned_share: 0,
+                    token_account_id: None,
+                },
+                Delegation {
+                    delegator_account_id: robin(),
+                    amount: 1,
+                    delegated_at: 0,
+                    earned_share: 0,
+                    token_account_id: None,
+                },
+                Delegation {
+                    delegator_account_id: robin(),
+                    amount: 1,
+                    delegated_at: 0,
+                    earned_share: 0,
+                    token_account_id: None,
+                },
+                Delegation {
+                    delegator_account_id: robin(),
+                    amount: 1,
+                    delegated_at: 0,
+                    earned_share: 0,
+                    token_account_id: None,
+                },
+            ],
+        );
+
+        let mut contract = Ledger {
+            stakes,
+            questions,
+            delegations,
+        };
+
+        let answer = contract.set_correct_answer(1, 1);
+        assert_eq!(answer.reward, 7);
+        // The 3 dust units stay put in the author's stake, same as
+        // distribute_reward leaves rounding dust behind.
+        assert_eq!(*contract.stakes.get(&(alice(), None)).unwrap(), 3u128);
+    }
+
+    #[test]
+    fn set_correct_answer_pays_whole_reward_without_delegators() {
+        let context = get_context(alice(), 0);
+        testing_env!(context);
+
+        let mut questions: HashMap<u32, Question> = HashMap::new();
+        questions.insert(
+            1,
+            Question {
+                content: "How do I look?".to_string(),
+                reward: 10,
+                author_account_id: alice(),
+                answers: vec![Answer {
+                    id: 1,
+                    content: "Perfect".to_string(),
+                    account_id: bob(),
+                    reward: 0,
+                    is_correct: false,
+                    upvote_credits: 0,
+                    credits_observed: 0,
+                }],
+                token_account_id: None,
+                deadline: 100,
+                distribution_mode: DistributionMode::WinnerTakesAll,
+                distributed: 0,
+            },
+        );
+        let mut stakes: Stakes = HashMap::new();
+        stakes.insert((alice(), None), 10);
+
+        let mut contract = Ledger {
+            stakes,
+            questions,
+            delegations: HashMap::new(),
+        };
+
+        let answer = contract.set_correct_answer(1, 1);
+        assert_eq!(answer.reward, 10);
+        assert_eq!(*contract.stakes.get(&(alice(), None)).unwrap(), 0u128);
+    }
+
+    #[test]
+    fn resolve_award_reverts_delegator_credits_on_failed_transfer() {
+        let context = VMContext {
+            current_account_id: "contract_owner".to_string(),
+            signer_account_id: alice(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "contract_owner".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 0,
+            epoch_height: 19,
+            account_balance: 10,
+            account_locked_balance: 0,
+            storage_usage: 0,
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(18),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+        };
+        testing_env!(
+            context,
+            VMConfig::default(),
+            RuntimeFeesConfig::default(),
+            vec![PromiseResult::Failed]
+        );
+
+        let mut questions: HashMap<u32, Question> = HashMap::new();
+        questions.insert(
+            1,
+            Question {
+                content: "How do I look?".to_string(),
+                reward: 10,
+                author_account_id: alice(),
+                answers: vec![Answer {
+                    id: 1,
+                    content: "Perfect".to_string(),
+                    account_id: bob(),
+                    reward: 7,
+                    is_correct: true,
+                    upvote_credits: 0,
+                    credits_observed: 0,
+                }],
+                token_account_id: None,
+                deadline: 100,
+                distribution_mode: DistributionMode::WinnerTakesAll,
+                distributed: 0,
+            },
+        );
+        let mut stakes: Stakes = HashMap::new();
+        stakes.insert((alice(), None), 0);
+        let mut delegations: HashMap<u32, Vec<Delegation>> = HashMap::new();
+        delegations.insert(
+            1,
+            vec![Delegation {
+                delegator_account_id: robin(),
+                amount: 10,
+                delegated_at: 0,
+                earned_share: 3,
+                token_account_id: None,
+            }],
+        );
+
+        let mut contract = Ledger {
+            stakes,
+            questions,
+            delegations,
+        };
+
+        contract.resolve_award(
+            1,
+            1,
+            alice(),
+            7,
+            None,
+            vec![DelegationCredit {
+                delegator_account_id: robin(),
+                amount: 3,
+            }],
+        );
+
+        assert_eq!(*contract.stakes.get(&(alice(), None)).unwrap(), 10u128);
+        assert_eq!(contract.delegations.get(&1u32).unwrap()[0].earned_share, 0);
+        let answer = &contract.questions.get(&1u32).unwrap().answers[0];
+        assert!(!answer.is_correct);
+        assert_eq!(answer.reward, 0);
+    }
+
+    #[test]
+    fn ft_on_transfer_funds_question_in_fungible_token() {
+        let context = get_context(alice(), 0);
+        testing_env!(context);
+        let mut contract = Ledger {
+            stakes: HashMap::new(),
+            questions: HashMap::new(),
+            delegations: HashMap::new(),
+        };
+
+        let msg = serde_json::to_string(&FtMessage::CreateQuestion {
+            content: "How do I look?".to_string(),
+            deadline: 100,
+            distribution_mode: DistributionMode::WinnerTakesAll,
+        })
+        .unwrap();
+        // predecessor_account_id in get_context is "alice", standing in for
+        // the token contract calling ft_on_transfer via ft_transfer_call.
+        let unspent = contract.ft_on_transfer(bob(), U128(10), msg);
+
+        assert_eq!(contract.questions.len(), 1);
+        assert_eq!(
+            contract.questions.get(&1u32).unwrap().token_account_id,
+            Some(alice())
+        );
+        assert_eq!(
+            *contract.stakes.get(&(bob(), Some(alice()))).unwrap(),
+            10u128
+        );
+        assert!(matches!(unspent, PromiseOrValue::Value(v) if v == U128(0)));
+    }
+
+    #[test]
+    fn ft_on_transfer_refunds_deposit_below_minimum() {
+        let context = get_context(alice(), 0);
+        testing_env!(context);
+        let mut contract = Ledger {
+            stakes: HashMap::new(),
+            questions: HashMap::new(),
+            delegations: HashMap::new(),
+        };
+
+        let msg = serde_json::to_string(&FtMessage::CreateQuestion {
+            content: "How do I look?".to_string(),
+            deadline: 100,
+            distribution_mode: DistributionMode::WinnerTakesAll,
+        })
+        .unwrap();
+        let unspent = contract.ft_on_transfer(bob(), U128(1), msg);
+
+        assert_eq!(contract.questions.len(), 0);
+        assert!(matches!(unspent, PromiseOrValue::Value(v) if v == U128(1)));
+    }
+
+    #[test]
+    fn resolve_award_reverts_stake_and_answer_on_failed_transfer() {
+        // predecessor == current_account_id, as if called back by our own
+        // scheduled Promise; the single recorded promise result is Failed.
+        let context = VMContext {
+            current_account_id: "contract_owner".to_string(),
+            signer_account_id: alice(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "contract_owner".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 0,
+            epoch_height: 19,
+            account_balance: 10,
+            account_locked_balance: 0,
+            storage_usage: 0,
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(18),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+        };
+        testing_env!(
+            context,
+            VMConfig::default(),
+            RuntimeFeesConfig::default(),
+            vec![PromiseResult::Failed]
+        );
+
+        let mut questions: HashMap<u32, Question> = HashMap::new();
+        questions.insert(
+            1,
+            Question {
+                content: "How do I look?".to_string(),
+                reward: 10,
+                author_account_id: alice(),
+                answers: vec![Answer {
+                    id: 1,
+                    content: "Perfect".to_string(),
+                    account_id: bob(),
+                    reward: 7,
+                    is_correct: true,
+                    upvote_credits: 0,
+                    credits_observed: 0,
+                }],
+                token_account_id: None,
+                deadline: 100,
+                distribution_mode: DistributionMode::WinnerTakesAll,
+                distributed: 0,
+            },
+        );
+        let mut stakes: Stakes = HashMap::new();
+        stakes.insert((alice(), None), 0);
+
+        let mut contract = Ledger {
+            stakes,
+            questions,
+            delegations: HashMap::new(),
+        };
+
+        contract.resolve_award(1, 1, alice(), 7, None, vec![]);
+
+        assert_eq!(*contract.stakes.get(&(alice(), None)).unwrap(), 7u128);
+        let answer = &contract.questions.get(&1u32).unwrap().answers[0];
+        assert!(!answer.is_correct);
+        assert_eq!(answer.reward, 0);
+    }
+
+    #[test]
+    fn delegate_and_undelegate_through_public_api_on_a_token_funded_question() {
+        let context = get_context(alice(), 0);
+        testing_env!(context);
+        let mut contract = Ledger {
+            stakes: HashMap::new(),
+            questions: HashMap::new(),
+            delegations: HashMap::new(),
+        };
+
+        let msg = serde_json::to_string(&FtMessage::CreateQuestion {
+            content: "How do I look?".to_string(),
+            deadline: 100,
+            distribution_mode: DistributionMode::WinnerTakesAll,
+        })
+        .unwrap();
+        // predecessor_account_id in get_context is "alice", standing in for
+        // the token contract calling ft_on_transfer via ft_transfer_call.
+        contract.ft_on_transfer(bob(), U128(10), msg);
+
+        let delegate_context = get_context(robin(), 5);
+        testing_env!(delegate_context);
+        contract.delegate(1);
+
+        let delegation = &contract.delegations.get(&1u32).unwrap()[0];
+        assert_eq!(delegation.delegator_account_id, robin());
+        assert_eq!(delegation.amount, 5);
+        // Delegation captured the question's funding token, not native NEAR.
+        assert_eq!(delegation.token_account_id, Some(alice()));
+
+        // Stand in for set_correct_answer having already credited a share
+        // denominated in the question's token.
+        contract.delegations.get_mut(&1u32).unwrap()[0].earned_share = 3;
+
+        let undelegate_context = get_context(robin(), 0);
+        testing_env!(undelegate_context);
+        contract.undelegate(1);
+
+        assert!(contract.delegations.get(&1u32).unwrap().is_empty());
+    }
+
+    #[test]
+    fn ft_on_transfer_upvotes_an_answer_in_the_question_token() {
+        let context = get_context(alice(), 0);
+        testing_env!(context);
+        let mut contract = Ledger {
+            stakes: HashMap::new(),
+            questions: HashMap::new(),
+            delegations: HashMap::new(),
+        };
+
+        let create_msg = serde_json::to_string(&FtMessage::CreateQuestion {
+            content: "How do I look?".to_string(),
+            deadline: 100,
+            distribution_mode: DistributionMode::WinnerTakesAll,
+        })
+        .unwrap();
+        // predecessor_account_id in get_context is "alice", standing in for
+        // the token contract calling ft_on_transfer via ft_transfer_call.
+        contract.ft_on_transfer(bob(), U128(10), create_msg);
+        contract
+            .questions
+            .get_mut(&1u32)
+            .unwrap()
+            .answers
+            .push(Answer {
+                id: 1,
+                content: "Perfect".to_string(),
+                account_id: robin(),
+                reward: 0,
+                is_correct: false,
+                upvote_credits: 0,
+                credits_observed: 0,
+            });
+
+        let upvote_msg = serde_json::to_string(&FtMessage::UpvoteAnswer {
+            question_id: 1,
+            answer_id: 1,
+        })
+        .unwrap();
+        let unspent = contract.ft_on_transfer(bob(), U128(2), upvote_msg);
+
+        let answer = &contract.questions.get(&1u32).unwrap().answers[0];
+        assert_eq!(answer.reward, 2);
+        assert_eq!(answer.upvote_credits, 2);
+        assert!(matches!(unspent, PromiseOrValue::Value(v) if v == U128(0)));
+    }
 }